@@ -14,11 +14,27 @@ pub enum AppError {
     ServerError(String),
 }
 use ring::{
+    aead::{self, Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM},
     digest::{self, SHA256},
     hmac,
     rand::{SecureRandom, SystemRandom},
 };
-use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use base64::{
+    engine::general_purpose::{STANDARD as BASE64, URL_SAFE_NO_PAD as BASE64_URL},
+    Engine,
+};
+use serde::{Deserialize, Serialize};
+
+/// Claims carried by a signed bearer token.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenClaims {
+    /// Subject the token was issued for.
+    pub sub: String,
+    /// Issued-at time, Unix seconds.
+    pub iat: u64,
+    /// Expiry time, Unix seconds.
+    pub exp: u64,
+}
 
 /// Cryptographic utilities using pure Rust implementations
 #[derive(Debug)]
@@ -73,16 +89,142 @@ impl Crypto {
         Ok(hex::encode(signature.as_ref()))
     }
     
-    /// Verify HMAC-SHA256
+    /// Verify HMAC-SHA256 in constant time
     pub fn verify_hmac_sha256(&self, key: &[u8], data: &[u8], expected: &str) -> Result<bool> {
-        let computed = self.hmac_sha256(key, data)?;
-        Ok(computed == expected)
+        let expected_bytes = hex::decode(expected)
+            .map_err(|e| anyhow::anyhow!("Invalid HMAC encoding: {}", e))?;
+        let key = hmac::Key::new(hmac::HMAC_SHA256, key);
+        Ok(hmac::verify(&key, data, &expected_bytes).is_ok())
     }
-    
-    /// Generate a secure token
-    pub fn generate_token(&self, length: usize) -> Result<String> {
-        let bytes = self.generate_random_bytes(length)?;
-        Ok(BASE64.encode(bytes).chars().take(length).collect())
+
+    /// Compare two secrets in constant time, to avoid leaking their contents
+    /// through response-timing side channels.
+    pub fn constant_time_eq(&self, a: &[u8], b: &[u8]) -> bool {
+        ring::constant_time::verify_slices_are_equal(a, b).is_ok()
+    }
+
+    /// Issue a self-contained, HMAC-signed bearer token for `subject` that
+    /// expires after `ttl_secs` seconds. The token is
+    /// `base64url(payload_json).base64url(hmac_sha256(key, payload_bytes))`.
+    pub fn issue_token(&self, key: &[u8], subject: &str, ttl_secs: u64) -> Result<String> {
+        let now = Self::unix_now()?;
+        let claims = TokenClaims {
+            sub: subject.to_string(),
+            iat: now,
+            exp: now + ttl_secs,
+        };
+
+        let payload = serde_json::to_vec(&claims)?;
+        let signature = hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, key), &payload);
+
+        Ok(format!(
+            "{}.{}",
+            BASE64_URL.encode(&payload),
+            BASE64_URL.encode(signature.as_ref())
+        ))
+    }
+
+    /// Verify a bearer token's signature and expiry, returning its claims.
+    pub fn verify_token(&self, key: &[u8], token: &str) -> Result<TokenClaims> {
+        let (payload_b64, signature_b64) = token
+            .split_once('.')
+            .ok_or_else(|| anyhow::anyhow!("Malformed token"))?;
+
+        let payload = BASE64_URL
+            .decode(payload_b64)
+            .map_err(|_| anyhow::anyhow!("Malformed token"))?;
+        let signature = BASE64_URL
+            .decode(signature_b64)
+            .map_err(|_| anyhow::anyhow!("Malformed token"))?;
+
+        // Constant-time comparison via `ring::hmac::verify` - a string/byte
+        // equality check here would leak timing information about the MAC.
+        hmac::verify(&hmac::Key::new(hmac::HMAC_SHA256, key), &payload, &signature)
+            .map_err(|_| anyhow::anyhow!("Invalid token signature"))?;
+
+        let claims: TokenClaims = serde_json::from_slice(&payload)
+            .map_err(|_| anyhow::anyhow!("Invalid token payload"))?;
+
+        if claims.exp <= Self::unix_now()? {
+            return Err(anyhow::anyhow!("Token expired"));
+        }
+
+        Ok(claims)
+    }
+
+    fn unix_now() -> Result<u64> {
+        Ok(std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|e| anyhow::anyhow!("System clock error: {}", e))?
+            .as_secs())
+    }
+
+    /// Encrypt `plaintext` under `key` with AES-256-GCM, returning
+    /// `base64(nonce || ciphertext || tag)`. A fresh nonce is drawn for every
+    /// call; the same (key, nonce) pair must never be reused.
+    pub fn encrypt(&self, key: &[u8], plaintext: &[u8]) -> Result<String> {
+        let less_safe_key = self.aead_key(key)?;
+
+        let mut nonce_bytes = [0u8; aead::NONCE_LEN];
+        self.rng
+            .fill(&mut nonce_bytes)
+            .map_err(|_| AppError::CryptoError("Failed to generate nonce".to_string()))?;
+
+        let mut in_out = plaintext.to_vec();
+        less_safe_key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce_bytes),
+                Aad::empty(),
+                &mut in_out,
+            )
+            .map_err(|_| AppError::CryptoError("Encryption failed".to_string()))?;
+
+        let mut output = Vec::with_capacity(nonce_bytes.len() + in_out.len());
+        output.extend_from_slice(&nonce_bytes);
+        output.extend_from_slice(&in_out);
+
+        Ok(BASE64.encode(output))
+    }
+
+    /// Decrypt a value produced by `encrypt`, returning the plaintext bytes.
+    pub fn decrypt(&self, key: &[u8], data: &str) -> Result<Vec<u8>> {
+        let less_safe_key = self.aead_key(key)?;
+
+        let raw = BASE64
+            .decode(data)
+            .map_err(|_| AppError::CryptoError("Invalid ciphertext encoding".to_string()))?;
+
+        if raw.len() < aead::NONCE_LEN {
+            return Err(AppError::CryptoError("Ciphertext too short".to_string()).into());
+        }
+        let (nonce_bytes, ciphertext) = raw.split_at(aead::NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce_bytes)
+            .map_err(|_| AppError::CryptoError("Invalid nonce".to_string()))?;
+
+        let mut in_out = ciphertext.to_vec();
+        let plaintext = less_safe_key
+            .open_in_place(nonce, Aad::empty(), &mut in_out)
+            // Deliberately opaque: don't reveal whether the nonce, tag, or
+            // ciphertext itself was the cause of the failure.
+            .map_err(|_| AppError::CryptoError("Decryption failed".to_string()))?;
+
+        Ok(plaintext.to_vec())
+    }
+
+    /// Build an AES-256-GCM key, rejecting anything other than a 32-byte key.
+    fn aead_key(&self, key: &[u8]) -> Result<LessSafeKey> {
+        if key.len() != AES_256_GCM.key_len() {
+            return Err(AppError::CryptoError(format!(
+                "AES-256-GCM requires a {}-byte key, got {}",
+                AES_256_GCM.key_len(),
+                key.len()
+            ))
+            .into());
+        }
+
+        let unbound_key = UnboundKey::new(&AES_256_GCM, key)
+            .map_err(|_| AppError::CryptoError("Invalid key".to_string()))?;
+        Ok(LessSafeKey::new(unbound_key))
     }
 }
 
@@ -126,4 +268,82 @@ mod tests {
         let hmac = crypto.hmac_sha256(key, data).unwrap();
         assert!(crypto.verify_hmac_sha256(key, data, &hmac).unwrap());
     }
+
+    #[test]
+    fn test_issue_and_verify_token() {
+        let crypto = Crypto::new();
+        let key = b"server_signing_key";
+
+        let token = crypto.issue_token(key, "alice", 3600).unwrap();
+        let claims = crypto.verify_token(key, &token).unwrap();
+
+        assert_eq!(claims.sub, "alice");
+        assert!(claims.exp > claims.iat);
+    }
+
+    #[test]
+    fn test_verify_token_rejects_expired() {
+        let crypto = Crypto::new();
+        let key = b"server_signing_key";
+
+        let token = crypto.issue_token(key, "alice", 0).unwrap();
+        assert!(crypto.verify_token(key, &token).is_err());
+    }
+
+    #[test]
+    fn test_verify_token_rejects_wrong_key() {
+        let crypto = Crypto::new();
+
+        let token = crypto.issue_token(b"key_one", "alice", 3600).unwrap();
+        assert!(crypto.verify_token(b"key_two", &token).is_err());
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        let crypto = Crypto::new();
+        let key = crypto.generate_random_bytes(32).unwrap();
+
+        let ciphertext = crypto.encrypt(&key, b"hello world").unwrap();
+        let plaintext = crypto.decrypt(&key, &ciphertext).unwrap();
+
+        assert_eq!(plaintext, b"hello world");
+    }
+
+    #[test]
+    fn test_encrypt_rejects_wrong_key_length() {
+        let crypto = Crypto::new();
+        assert!(crypto.encrypt(b"too_short", b"hello world").is_err());
+    }
+
+    #[test]
+    fn test_decrypt_rejects_tampered_ciphertext() {
+        let crypto = Crypto::new();
+        let key = crypto.generate_random_bytes(32).unwrap();
+
+        let mut ciphertext = BASE64.decode(crypto.encrypt(&key, b"hello world").unwrap()).unwrap();
+        *ciphertext.last_mut().unwrap() ^= 0xFF;
+        let tampered = BASE64.encode(ciphertext);
+
+        assert!(crypto.decrypt(&key, &tampered).is_err());
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        let crypto = Crypto::new();
+
+        assert!(crypto.constant_time_eq(b"admin-secret", b"admin-secret"));
+        assert!(!crypto.constant_time_eq(b"admin-secret", b"wrong-secret"));
+        assert!(!crypto.constant_time_eq(b"admin-secret", b"admin-secre"));
+    }
+
+    #[test]
+    fn test_encrypt_uses_a_fresh_nonce_each_call() {
+        let crypto = Crypto::new();
+        let key = crypto.generate_random_bytes(32).unwrap();
+
+        let first = crypto.encrypt(&key, b"hello world").unwrap();
+        let second = crypto.encrypt(&key, b"hello world").unwrap();
+
+        assert_ne!(first, second);
+    }
 }
@@ -3,32 +3,22 @@
 
 use anyhow::Result;
 use log::info;
-use std::env;
 
-mod crypto;
-mod server;
+use application::server::{self, Config};
 
 #[tokio::main]
 async fn main() -> Result<()> {
     // Initialize logging
     env_logger::init();
-    
+
     info!("Starting Rust application...");
-    
-    // Get port from environment or use default
-    let port = env::var("PORT")
-        .unwrap_or_else(|_| "5000".to_string())
-        .parse::<u16>()
-        .unwrap_or(5000);
-    
-    // Get host from environment or use default
-    let host = env::var("HOST")
-        .unwrap_or_else(|_| "0.0.0.0".to_string());
-    
-    info!("Server will start on {}:{}", host, port);
-    
+
+    let config = Config::from_env();
+
+    info!("Server will start on {}:{}", config.server_host, config.server_port);
+
     // Start the server
-    server::start_server(&host, port).await?;
-    
+    server::start_server(config).await?;
+
     Ok(())
 }
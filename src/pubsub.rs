@@ -0,0 +1,126 @@
+//! Topic-based publish/subscribe backing the `/ws/:topic` route.
+//!
+//! Each topic is backed by a `tokio::sync::broadcast` channel created
+//! lazily on first subscribe or publish, and torn down once its last
+//! subscriber disconnects so topic memory doesn't grow without bound.
+
+use std::collections::HashMap;
+use tokio::sync::{broadcast, RwLock};
+use warp::ws::Message;
+
+/// Capacity of each topic's broadcast channel; a subscriber that falls this
+/// far behind the publisher will see a `RecvError::Lagged` on its next recv.
+const CHANNEL_CAPACITY: usize = 128;
+
+/// Registry of per-topic broadcast channels.
+#[derive(Debug, Default)]
+pub struct TopicRegistry {
+    topics: RwLock<HashMap<String, broadcast::Sender<Message>>>,
+}
+
+impl TopicRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribe to `topic`, creating its channel if this is the first subscriber.
+    pub async fn subscribe(&self, topic: &str) -> broadcast::Receiver<Message> {
+        let mut topics = self.topics.write().await;
+        match topics.get(topic) {
+            Some(sender) => sender.subscribe(),
+            None => {
+                let (sender, receiver) = broadcast::channel(CHANNEL_CAPACITY);
+                topics.insert(topic.to_string(), sender);
+                receiver
+            }
+        }
+    }
+
+    /// Publish `message` to every current subscriber of `topic`, creating the
+    /// topic's channel if needed. Returns the number of subscribers reached.
+    pub async fn publish(&self, topic: &str, message: Message) -> usize {
+        let mut topics = self.topics.write().await;
+        let sender = topics
+            .entry(topic.to_string())
+            .or_insert_with(|| broadcast::channel(CHANNEL_CAPACITY).0);
+        let reached = sender.send(message).unwrap_or(0);
+
+        // Nobody is listening anymore (or ever was) - don't keep the channel around.
+        if sender.receiver_count() == 0 {
+            topics.remove(topic);
+        }
+
+        reached
+    }
+
+    /// Remove `topic`'s channel if it currently has no subscribers. Callers
+    /// that hold a receiver for `topic` must drop it before calling this, or
+    /// the topic will look non-empty and won't be pruned.
+    pub async fn prune(&self, topic: &str) {
+        let mut topics = self.topics.write().await;
+        if topics.get(topic).is_some_and(|sender| sender.receiver_count() == 0) {
+            topics.remove(topic);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn publish_reaches_all_subscribers() {
+        let registry = TopicRegistry::new();
+
+        let mut sub1 = registry.subscribe("news").await;
+        let mut sub2 = registry.subscribe("news").await;
+
+        let reached = registry.publish("news", Message::text("hello")).await;
+        assert_eq!(reached, 2);
+
+        assert_eq!(sub1.recv().await.unwrap(), Message::text("hello"));
+        assert_eq!(sub2.recv().await.unwrap(), Message::text("hello"));
+    }
+
+    #[tokio::test]
+    async fn publish_with_no_subscribers_drops_the_topic() {
+        let registry = TopicRegistry::new();
+
+        let reached = registry.publish("empty", Message::text("hi")).await;
+        assert_eq!(reached, 0);
+        assert!(registry.topics.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn prune_removes_topic_with_no_subscribers() {
+        let registry = TopicRegistry::new();
+
+        let sub = registry.subscribe("news").await;
+        drop(sub);
+        registry.prune("news").await;
+
+        assert!(registry.topics.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn prune_keeps_topic_with_subscribers() {
+        let registry = TopicRegistry::new();
+
+        let _sub = registry.subscribe("news").await;
+        registry.prune("news").await;
+
+        assert!(!registry.topics.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn late_subscriber_does_not_see_past_messages() {
+        let registry = TopicRegistry::new();
+
+        registry.publish("news", Message::text("before")).await;
+        let mut sub = registry.subscribe("news").await;
+        registry.publish("news", Message::text("after")).await;
+
+        assert_eq!(sub.recv().await.unwrap(), Message::text("after"));
+    }
+}
@@ -2,28 +2,125 @@
 //! Uses warp for HTTP server functionality
 
 use crate::crypto::Crypto;
+use crate::pubsub::TopicRegistry;
+use crate::storage::{DiskStorage, MemoryStorage, Storage, StorageBackend};
 use anyhow::Result;
+use futures_util::{SinkExt, StreamExt};
 use log::{error, info, warn};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use std::convert::Infallible;
+use std::fs::File;
+use std::io::BufReader;
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use warp::{http::StatusCode, Filter, Rejection, Reply};
+use warp::{http::StatusCode, ws::Message, Filter, Rejection, Reply};
+
+/// Server configuration
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub server_host: String,
+    pub server_port: u16,
+    pub log_level: String,
+    /// Path to a PEM certificate chain; when set together with `tls_key_path`,
+    /// the server terminates TLS itself instead of serving plaintext HTTP.
+    pub tls_cert_path: Option<String>,
+    /// Path to a PEM private key (PKCS#8 or RSA) matching `tls_cert_path`.
+    pub tls_key_path: Option<String>,
+    /// `Content-Security-Policy` header value applied to every response.
+    pub csp_policy: String,
+    /// `Permissions-Policy` header value applied to every response.
+    pub permissions_policy: String,
+    /// Which `Storage` implementation to use for the `/data` endpoints.
+    pub storage_backend: StorageBackend,
+    /// Directory the disk storage backend persists entries under.
+    pub storage_dir: String,
+    /// Maximum number of entries the disk storage backend will hold.
+    pub storage_max_entries: Option<usize>,
+    /// Shared secret required to issue a bearer token via `POST /crypto`
+    /// (`operation: "token"`). Token issuance is refused when unset.
+    pub admin_secret: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            server_host: "0.0.0.0".to_string(),
+            server_port: 5000,
+            log_level: "info".to_string(),
+            tls_cert_path: None,
+            tls_key_path: None,
+            csp_policy: "default-src 'self'".to_string(),
+            permissions_policy: "geolocation=(), microphone=(), camera=()".to_string(),
+            storage_backend: StorageBackend::Memory,
+            storage_dir: "data".to_string(),
+            storage_max_entries: None,
+            admin_secret: None,
+        }
+    }
+}
+
+impl Config {
+    /// Load configuration from environment variables
+    pub fn from_env() -> Self {
+        Self {
+            server_host: std::env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
+            server_port: std::env::var("PORT")
+                .unwrap_or_else(|_| "5000".to_string())
+                .parse()
+                .unwrap_or(5000),
+            log_level: std::env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string()),
+            tls_cert_path: std::env::var("TLS_CERT").ok(),
+            tls_key_path: std::env::var("TLS_KEY").ok(),
+            csp_policy: std::env::var("CSP_POLICY")
+                .unwrap_or_else(|_| "default-src 'self'".to_string()),
+            permissions_policy: std::env::var("PERMISSIONS_POLICY").unwrap_or_else(|_| {
+                "geolocation=(), microphone=(), camera=()".to_string()
+            }),
+            storage_backend: std::env::var("STORAGE_BACKEND")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or_default(),
+            storage_dir: std::env::var("STORAGE_DIR").unwrap_or_else(|_| "data".to_string()),
+            storage_max_entries: std::env::var("STORAGE_MAX_ENTRIES")
+                .ok()
+                .and_then(|v| v.parse().ok()),
+            admin_secret: std::env::var("ADMIN_SECRET").ok(),
+        }
+    }
+}
 
 /// Application state
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub crypto: Arc<Crypto>,
-    pub data: Arc<RwLock<HashMap<String, String>>>,
+    pub storage: Arc<dyn Storage + Send + Sync>,
+    pub topics: Arc<TopicRegistry>,
+    /// Server-generated key used to sign and verify bearer tokens.
+    pub signing_key: Arc<Vec<u8>>,
+    /// Shared secret required to issue a bearer token; `None` disables
+    /// issuance entirely.
+    pub admin_secret: Option<String>,
 }
 
 impl AppState {
-    pub fn new() -> Self {
-        Self {
-            crypto: Arc::new(Crypto::new()),
-            data: Arc::new(RwLock::new(HashMap::new())),
-        }
+    pub fn new(config: &Config) -> Result<Self> {
+        let crypto = Crypto::new();
+        let signing_key = crypto.generate_random_bytes(32)?;
+
+        let storage: Arc<dyn Storage + Send + Sync> = match config.storage_backend {
+            StorageBackend::Memory => Arc::new(MemoryStorage::new()),
+            StorageBackend::Disk => Arc::new(DiskStorage::new(
+                config.storage_dir.clone(),
+                config.storage_max_entries,
+            )?),
+        };
+
+        Ok(Self {
+            crypto: Arc::new(crypto),
+            storage,
+            topics: Arc::new(TopicRegistry::new()),
+            signing_key: Arc::new(signing_key),
+            admin_secret: config.admin_secret.clone(),
+        })
     }
 }
 
@@ -49,6 +146,15 @@ struct CryptoRequest {
     operation: String,
     data: Option<String>,
     length: Option<usize>,
+    /// Subject to embed in an issued bearer token (`operation: "token"`).
+    subject: Option<String>,
+    /// Token lifetime in seconds (`operation: "token"`); defaults to 3600.
+    ttl_secs: Option<u64>,
+    /// Hex-encoded AES-256-GCM key (`operation: "encrypt"`/`"decrypt"`).
+    key: Option<String>,
+    /// Shared admin secret required for `operation: "token"`; must match the
+    /// server's configured `ADMIN_SECRET`.
+    admin_secret: Option<String>,
 }
 
 /// Crypto operation response
@@ -58,10 +164,14 @@ struct CryptoResponse {
     operation: String,
 }
 
-/// Start the HTTP server
-pub async fn start_server(host: &str, port: u16) -> Result<()> {
-    let state = AppState::new();
-    
+/// Build every route, wrapped in CORS, rejection recovery, and the
+/// hardening header layer. Split out from `start_server` so tests can
+/// exercise the full filter chain via `warp::test::request()` without
+/// binding a real TCP listener.
+fn build_routes(
+    config: &Config,
+    state: AppState,
+) -> impl Filter<Extract = (impl Reply,), Error = Infallible> + Clone {
     // Health check endpoint
     let health = warp::path("health")
         .and(warp::get())
@@ -80,60 +190,162 @@ pub async fn start_server(host: &str, port: u16) -> Result<()> {
                 error: None,
             })
         });
-    
+
     // Crypto operations endpoint
     let crypto_ops = warp::path("crypto")
         .and(warp::post())
         .and(warp::body::json())
         .and(with_state(state.clone()))
         .and_then(handle_crypto_operation);
-    
-    // Data storage endpoints
+
+    // Data storage endpoints (require a valid bearer token)
     let store_data = warp::path("data")
         .and(warp::path::param::<String>())
         .and(warp::post())
+        .and(require_auth(state.clone()))
         .and(warp::body::bytes())
         .and(with_state(state.clone()))
         .and_then(handle_store_data);
-    
+
     let get_data = warp::path("data")
         .and(warp::path::param::<String>())
         .and(warp::get())
+        .and(require_auth(state.clone()))
         .and(with_state(state.clone()))
         .and_then(handle_get_data);
-    
+
+    // WebSocket pub/sub: subscribe to a topic and receive every publish to it
+    let ws_route = warp::path("ws")
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(warp::ws())
+        .and(with_state(state.clone()))
+        .map(|topic: String, ws: warp::ws::Ws, state: AppState| {
+            ws.on_upgrade(move |socket| handle_ws_connection(socket, topic, state))
+        });
+
+    // Publish a message to every current subscriber of a topic (requires a valid bearer token)
+    let publish_route = warp::path("publish")
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(require_auth(state.clone()))
+        .and(warp::body::bytes())
+        .and(with_state(state.clone()))
+        .and_then(handle_publish);
+
     // Static files for frontend
     let static_files = warp::path("static")
-        .and(warp::fs::dir("static"));
-    
+        .and(warp::fs::dir("static"))
+        .map(|reply| warp::reply::with_header(reply, "Cache-Control", "public, max-age=86400"));
+
     // Root endpoint serves a simple HTML page
     let root = warp::path::end()
         .and(warp::get())
         .map(serve_index);
-    
-    // Combine all routes
-    let routes = health
+
+    health
         .or(crypto_ops)
         .or(store_data)
         .or(get_data)
+        .or(ws_route)
+        .or(publish_route)
         .or(static_files)
         .or(root)
         .with(warp::cors().allow_any_origin())
-        .recover(handle_rejection);
-    
-    info!("Server starting on {}:{}", host, port);
-    
+        .recover(handle_rejection)
+        .with(security_headers(config))
+}
+
+/// Start the HTTP server
+pub async fn start_server(config: Config) -> Result<()> {
+    let host = config.server_host.as_str();
+    let port = config.server_port;
+    let state = AppState::new(&config)?;
+    let routes = build_routes(&config, state);
+
     let addr: std::net::SocketAddr = format!("{}:{}", host, port)
         .parse()
         .map_err(|e| anyhow::anyhow!("Invalid address: {}", e))?;
-    
-    warp::serve(routes)
-        .run(addr)
-        .await;
-    
+
+    match (&config.tls_cert_path, &config.tls_key_path) {
+        (Some(cert_path), Some(key_path)) => {
+            // Validate the PEM files eagerly so a bad cert/key fails at
+            // startup instead of on the first incoming connection.
+            load_tls_config(cert_path, key_path)?;
+
+            info!("Server starting on {}:{} (TLS enabled)", host, port);
+
+            warp::serve(routes)
+                .tls()
+                .cert_path(cert_path)
+                .key_path(key_path)
+                .run(addr)
+                .await;
+        }
+        (None, None) => {
+            info!("Server starting on {}:{} (plaintext, no TLS configured)", host, port);
+
+            warp::serve(routes).run(addr).await;
+        }
+        (cert, key) => {
+            warn!(
+                "Incomplete TLS configuration (TLS_CERT {}, TLS_KEY {}) - falling back to plaintext",
+                if cert.is_some() { "set" } else { "missing" },
+                if key.is_some() { "set" } else { "missing" },
+            );
+            info!("Server starting on {}:{} (plaintext)", host, port);
+
+            warp::serve(routes).run(addr).await;
+        }
+    }
+
     Ok(())
 }
 
+/// Load and validate a PEM certificate chain and private key, accepting
+/// either PKCS#8 or RSA-formatted keys.
+fn load_tls_config(cert_path: &str, key_path: &str) -> Result<rustls::ServerConfig> {
+    let cert_file = File::open(cert_path)
+        .map_err(|e| anyhow::anyhow!("Failed to open TLS_CERT '{}': {}", cert_path, e))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(cert_file))
+        .map_err(|e| anyhow::anyhow!("Failed to parse TLS_CERT '{}': {}", cert_path, e))?
+        .into_iter()
+        .map(rustls::Certificate)
+        .collect::<Vec<_>>();
+
+    if certs.is_empty() {
+        return Err(anyhow::anyhow!("No certificates found in '{}'", cert_path));
+    }
+
+    let key_file = File::open(key_path)
+        .map_err(|e| anyhow::anyhow!("Failed to open TLS_KEY '{}': {}", key_path, e))?;
+    let mut key_reader = BufReader::new(key_file);
+
+    let mut keys = rustls_pemfile::pkcs8_private_keys(&mut key_reader)
+        .map_err(|e| anyhow::anyhow!("Failed to parse TLS_KEY '{}': {}", key_path, e))?;
+
+    if keys.is_empty() {
+        let key_file = File::open(key_path)
+            .map_err(|e| anyhow::anyhow!("Failed to open TLS_KEY '{}': {}", key_path, e))?;
+        keys = rustls_pemfile::rsa_private_keys(&mut BufReader::new(key_file))
+            .map_err(|e| anyhow::anyhow!("Failed to parse TLS_KEY '{}': {}", key_path, e))?;
+    }
+
+    let key = keys
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("No private key found in '{}'", key_path))?;
+
+    let config = rustls::ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, rustls::PrivateKey(key))
+        .map_err(|e| anyhow::anyhow!("Invalid TLS certificate/key pair: {}", e))?;
+
+    Ok(config)
+}
+
 /// Helper to pass state to handlers
 fn with_state(
     state: AppState,
@@ -141,6 +353,36 @@ fn with_state(
     warp::any().map(move || state.clone())
 }
 
+/// Rejection used when a request is missing a valid bearer token.
+#[derive(Debug)]
+struct Unauthorized;
+
+impl warp::reject::Reject for Unauthorized {}
+
+/// Require a valid `Authorization: Bearer <token>` header, rejecting with
+/// 401 otherwise. Extracts nothing; combine with `with_state` for handlers
+/// that also need the application state.
+fn require_auth(state: AppState) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and_then(move |header: Option<String>| {
+            let state = state.clone();
+            async move {
+                let token = header
+                    .as_deref()
+                    .and_then(|value| value.strip_prefix("Bearer "))
+                    .ok_or_else(|| warp::reject::custom(Unauthorized))?;
+
+                state
+                    .crypto
+                    .verify_token(&state.signing_key, token)
+                    .map_err(|_| warp::reject::custom(Unauthorized))?;
+
+                Ok::<(), Rejection>(())
+            }
+        })
+        .untuple_one()
+}
+
 /// Handle crypto operations
 async fn handle_crypto_operation(
     req: CryptoRequest,
@@ -164,10 +406,38 @@ async fn handle_crypto_operation(
                 Err(anyhow::anyhow!("No data provided for hash"))
             }
         }
-        "token" => {
-            let len = req.length.unwrap_or(32);
-            state.crypto.generate_token(len)
-        }
+        "token" => match &state.admin_secret {
+            None => Err(anyhow::anyhow!(
+                "Token issuance is disabled: ADMIN_SECRET is not configured"
+            )),
+            Some(expected) => {
+                let provided = req.admin_secret.as_deref().unwrap_or_default();
+                if !state.crypto.constant_time_eq(provided.as_bytes(), expected.as_bytes()) {
+                    Err(anyhow::anyhow!("Invalid admin credential"))
+                } else {
+                    let subject = req.subject.clone().unwrap_or_else(|| "anonymous".to_string());
+                    let ttl_secs = req.ttl_secs.unwrap_or(3600);
+                    state.crypto.issue_token(&state.signing_key, &subject, ttl_secs)
+                }
+            }
+        },
+        "encrypt" => match (req.data.as_deref(), req.key.as_deref()) {
+            (Some(data), Some(key_hex)) => hex::decode(key_hex)
+                .map_err(|e| anyhow::anyhow!("Invalid key encoding: {}", e))
+                .and_then(|key| state.crypto.encrypt(&key, data.as_bytes())),
+            _ => Err(anyhow::anyhow!("encrypt requires 'data' and 'key'")),
+        },
+        "decrypt" => match (req.data.as_deref(), req.key.as_deref()) {
+            (Some(data), Some(key_hex)) => hex::decode(key_hex)
+                .map_err(|e| anyhow::anyhow!("Invalid key encoding: {}", e))
+                .and_then(|key| {
+                    state
+                        .crypto
+                        .decrypt(&key, data)
+                        .map(|plaintext| String::from_utf8_lossy(&plaintext).to_string())
+                }),
+            _ => Err(anyhow::anyhow!("decrypt requires 'data' and 'key'")),
+        },
         _ => Err(anyhow::anyhow!("Unknown operation: {}", req.operation)),
     };
     
@@ -201,19 +471,25 @@ async fn handle_store_data(
     state: AppState,
 ) -> std::result::Result<impl Reply, Rejection> {
     let data_str = String::from_utf8_lossy(&data).to_string();
-    
-    {
-        let mut storage = state.data.write().await;
-        storage.insert(key.clone(), data_str);
+
+    match state.storage.put(&key, data_str).await {
+        Ok(()) => {
+            info!("Data stored for key: {}", key);
+            Ok(warp::reply::json(&ApiResponse {
+                success: true,
+                data: Some(format!("Data stored for key: {}", key)),
+                error: None,
+            }))
+        }
+        Err(e) => {
+            error!("Failed to store data for key '{}': {}", key, e);
+            Ok(warp::reply::json(&ApiResponse::<String> {
+                success: false,
+                data: None,
+                error: Some(e.to_string()),
+            }))
+        }
     }
-    
-    info!("Data stored for key: {}", key);
-    
-    Ok(warp::reply::json(&ApiResponse {
-        success: true,
-        data: Some(format!("Data stored for key: {}", key)),
-        error: None,
-    }))
 }
 
 /// Handle data retrieval
@@ -221,18 +497,16 @@ async fn handle_get_data(
     key: String,
     state: AppState,
 ) -> std::result::Result<impl Reply, Rejection> {
-    let storage = state.data.read().await;
-    
-    match storage.get(&key) {
-        Some(data) => {
+    match state.storage.get(&key).await {
+        Ok(Some(data)) => {
             info!("Data retrieved for key: {}", key);
             Ok(warp::reply::json(&ApiResponse {
                 success: true,
-                data: Some(data.clone()),
+                data: Some(data),
                 error: None,
             }))
         }
-        None => {
+        Ok(None) => {
             warn!("No data found for key: {}", key);
             Ok(warp::reply::json(&ApiResponse::<String> {
                 success: false,
@@ -240,12 +514,111 @@ async fn handle_get_data(
                 error: Some(format!("No data found for key: {}", key)),
             }))
         }
+        Err(e) => {
+            error!("Failed to retrieve data for key '{}': {}", key, e);
+            Ok(warp::reply::json(&ApiResponse::<String> {
+                success: false,
+                data: None,
+                error: Some(e.to_string()),
+            }))
+        }
+    }
+}
+
+/// Drive a single WebSocket connection: forward every broadcast on `topic`
+/// to the client, and fan out every text or binary message the client sends
+/// up the socket to all of the topic's subscribers, this connection included.
+async fn handle_ws_connection(socket: warp::ws::WebSocket, topic: String, state: AppState) {
+    let (mut ws_tx, mut ws_rx) = socket.split();
+    let mut topic_rx = state.topics.subscribe(&topic).await;
+
+    info!("WebSocket subscribed to topic: {}", topic);
+
+    loop {
+        tokio::select! {
+            broadcast = topic_rx.recv() => {
+                match broadcast {
+                    Ok(message) => {
+                        if ws_tx.send(message).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(_) => break,
+                }
+            }
+            incoming = ws_rx.next() => {
+                match incoming {
+                    Some(Ok(message)) if message.is_close() => break,
+                    Some(Ok(message)) => {
+                        if message.is_text() || message.is_binary() {
+                            state.topics.publish(&topic, message).await;
+                        }
+                    }
+                    _ => break,
+                }
+            }
+        }
     }
+
+    // Drop our subscription before pruning, so `receiver_count()` reflects
+    // that this connection is really gone and doesn't race the cleanup.
+    drop(topic_rx);
+    state.topics.prune(&topic).await;
+
+    info!("WebSocket unsubscribed from topic: {}", topic);
+}
+
+/// Handle publishing a message to a topic's subscribers
+async fn handle_publish(
+    topic: String,
+    body: bytes::Bytes,
+    state: AppState,
+) -> std::result::Result<impl Reply, Rejection> {
+    let text = String::from_utf8_lossy(&body).to_string();
+    let reached = state.topics.publish(&topic, Message::text(text)).await;
+
+    info!("Published to topic '{}', reached {} subscriber(s)", topic, reached);
+
+    Ok(warp::reply::json(&ApiResponse {
+        success: true,
+        data: Some(format!("Published to {} subscriber(s)", reached)),
+        error: None,
+    }))
 }
 
+const INDEX_HTML: &str = include_str!("../web/index.html");
+
 /// Serve the main HTML page
 fn serve_index() -> impl Reply {
-    warp::reply::html(include_str!("../web/index.html"))	
+    static ETAG: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+    let etag = ETAG.get_or_init(|| format!("\"{}\"", Crypto::new().sha256(INDEX_HTML.as_bytes())));
+
+    let reply = warp::reply::html(INDEX_HTML);
+    let reply = warp::reply::with_header(reply, "Cache-Control", "public, max-age=3600");
+    warp::reply::with_header(reply, "ETag", etag.as_str())
+}
+
+/// Build the hardening response-header layer applied to every route.
+///
+/// None of these header names overlap with `Connection`/`Upgrade`, so it's
+/// safe to apply on top of the `/ws/:topic` upgrade response as well.
+fn security_headers(config: &Config) -> warp::filters::reply::WithHeaders {
+    let mut headers = warp::http::HeaderMap::new();
+    headers.insert("X-Content-Type-Options", warp::http::HeaderValue::from_static("nosniff"));
+    headers.insert("X-Frame-Options", warp::http::HeaderValue::from_static("DENY"));
+    headers.insert(
+        "Content-Security-Policy",
+        warp::http::HeaderValue::from_str(&config.csp_policy)
+            .unwrap_or_else(|_| warp::http::HeaderValue::from_static("default-src 'self'")),
+    );
+    headers.insert("Referrer-Policy", warp::http::HeaderValue::from_static("no-referrer"));
+    headers.insert(
+        "Permissions-Policy",
+        warp::http::HeaderValue::from_str(&config.permissions_policy).unwrap_or_else(|_| {
+            warp::http::HeaderValue::from_static("geolocation=(), microphone=(), camera=()")
+        }),
+    );
+    warp::reply::with::headers(headers)
 }
 
 /// Handle rejections
@@ -256,6 +629,8 @@ async fn handle_rejection(err: Rejection) -> std::result::Result<impl Reply, Inf
         (StatusCode::NOT_FOUND, "Not Found")
     } else if err.find::<warp::filters::body::BodyDeserializeError>().is_some() {
         (StatusCode::BAD_REQUEST, "Invalid JSON")
+    } else if err.find::<Unauthorized>().is_some() {
+        (StatusCode::UNAUTHORIZED, "Unauthorized")
     } else {
         (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error")
     };
@@ -268,3 +643,158 @@ async fn handle_rejection(err: Rejection) -> std::result::Result<impl Reply, Inf
     
     Ok(warp::reply::with_status(json, code))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build just the `/ws/:topic` route, so tests can drive
+    /// `handle_ws_connection` through `warp::test::ws()` without starting a
+    /// real TCP listener.
+    fn ws_route(state: AppState) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+        warp::path("ws")
+            .and(warp::path::param::<String>())
+            .and(warp::path::end())
+            .and(warp::ws())
+            .and(with_state(state))
+            .map(|topic: String, ws: warp::ws::Ws, state: AppState| {
+                ws.on_upgrade(move |socket| handle_ws_connection(socket, topic, state))
+            })
+    }
+
+    #[tokio::test]
+    async fn publish_reaches_both_sockets_connected_to_a_topic() {
+        let state = AppState::new(&Config::default()).unwrap();
+        let route = ws_route(state.clone());
+
+        let mut client1 = warp::test::ws()
+            .path("/ws/news")
+            .handshake(route.clone())
+            .await
+            .expect("client1 handshake");
+        let mut client2 = warp::test::ws()
+            .path("/ws/news")
+            .handshake(route)
+            .await
+            .expect("client2 handshake");
+
+        // Give both sockets' upgrade tasks a chance to reach
+        // `state.topics.subscribe` before we publish.
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        let reached = state.topics.publish("news", Message::text("hello")).await;
+        assert_eq!(reached, 2);
+
+        assert_eq!(client1.recv().await.unwrap().to_str().unwrap(), "hello");
+        assert_eq!(client2.recv().await.unwrap().to_str().unwrap(), "hello");
+    }
+
+    #[tokio::test]
+    async fn security_headers_are_present_on_recovered_error_responses() {
+        let config = Config::default();
+        let state = AppState::new(&config).unwrap();
+        let routes = build_routes(&config, state);
+
+        // No route matches, so this reply comes entirely out of
+        // `handle_rejection`'s `.recover()` arm, not the happy path.
+        let resp = warp::test::request()
+            .path("/this-route-does-not-exist")
+            .reply(&routes)
+            .await;
+
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert_eq!(resp.headers().get("X-Content-Type-Options").unwrap(), "nosniff");
+        assert_eq!(resp.headers().get("X-Frame-Options").unwrap(), "DENY");
+        assert!(resp.headers().get("Content-Security-Policy").is_some());
+    }
+
+    #[tokio::test]
+    async fn data_and_publish_routes_reject_missing_or_garbage_auth() {
+        let config = Config::default();
+        let state = AppState::new(&config).unwrap();
+        let routes = build_routes(&config, state);
+
+        let resp = warp::test::request()
+            .method("GET")
+            .path("/data/some-key")
+            .reply(&routes)
+            .await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let resp = warp::test::request()
+            .method("GET")
+            .path("/data/some-key")
+            .header("authorization", "garbage")
+            .reply(&routes)
+            .await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/publish/news")
+            .body("hello")
+            .reply(&routes)
+            .await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn crypto_token_operation_refuses_missing_or_wrong_admin_secret() {
+        let mut config = Config::default();
+        config.admin_secret = Some("s3cr3t".to_string());
+        let state = AppState::new(&config).unwrap();
+        let routes = build_routes(&config, state);
+
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/crypto")
+            .json(&serde_json::json!({ "operation": "token", "subject": "alice" }))
+            .reply(&routes)
+            .await;
+        let body: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(body["success"], false);
+
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/crypto")
+            .json(&serde_json::json!({
+                "operation": "token",
+                "subject": "alice",
+                "admin_secret": "wrong",
+            }))
+            .reply(&routes)
+            .await;
+        let body: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(body["success"], false);
+    }
+
+    #[tokio::test]
+    async fn correct_admin_secret_issues_a_token_accepted_by_require_auth() {
+        let mut config = Config::default();
+        config.admin_secret = Some("s3cr3t".to_string());
+        let state = AppState::new(&config).unwrap();
+        let routes = build_routes(&config, state);
+
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/crypto")
+            .json(&serde_json::json!({
+                "operation": "token",
+                "subject": "alice",
+                "admin_secret": "s3cr3t",
+            }))
+            .reply(&routes)
+            .await;
+        let body: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(body["success"], true);
+        let token = body["data"]["result"].as_str().unwrap();
+
+        let resp = warp::test::request()
+            .method("GET")
+            .path("/data/some-key")
+            .header("authorization", format!("Bearer {}", token))
+            .reply(&routes)
+            .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+}
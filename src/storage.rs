@@ -0,0 +1,341 @@
+//! Pluggable storage backends for the `/data` key/value API.
+//!
+//! `AppState` holds an `Arc<dyn Storage + Send + Sync>` so handlers stay
+//! backend-agnostic; the concrete backend is chosen via `Config::storage_backend`.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tokio::sync::RwLock;
+
+/// Selects which `Storage` implementation `AppState` uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageBackend {
+    /// Data lives only in process memory and is lost on restart.
+    Memory,
+    /// Data is persisted to disk, one file per key.
+    Disk,
+}
+
+impl Default for StorageBackend {
+    fn default() -> Self {
+        StorageBackend::Memory
+    }
+}
+
+impl std::str::FromStr for StorageBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "memory" => Ok(StorageBackend::Memory),
+            "disk" => Ok(StorageBackend::Disk),
+            other => Err(format!(
+                "Unknown storage backend '{}', expected 'memory' or 'disk'",
+                other
+            )),
+        }
+    }
+}
+
+/// A pluggable key/value store.
+#[async_trait]
+pub trait Storage: std::fmt::Debug {
+    async fn get(&self, key: &str) -> Result<Option<String>>;
+    async fn put(&self, key: &str, value: String) -> Result<()>;
+    async fn delete(&self, key: &str) -> Result<()>;
+    async fn list_keys(&self) -> Result<Vec<String>>;
+}
+
+/// In-memory store; all data is lost on restart.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    data: RwLock<HashMap<String, String>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    async fn get(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.data.read().await.get(key).cloned())
+    }
+
+    async fn put(&self, key: &str, value: String) -> Result<()> {
+        self.data.write().await.insert(key.to_string(), value);
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.data.write().await.remove(key);
+        Ok(())
+    }
+
+    async fn list_keys(&self) -> Result<Vec<String>> {
+        Ok(self.data.read().await.keys().cloned().collect())
+    }
+}
+
+/// File-backed store: one file per key under `dir`, written
+/// write-to-temp-then-rename so a crash mid-write can't corrupt an entry.
+#[derive(Debug)]
+pub struct DiskStorage {
+    dir: PathBuf,
+    max_entries: Option<usize>,
+    // Serializes writes (and size-bound checks) against each other; reads
+    // don't need to wait on it since renames are atomic.
+    write_lock: RwLock<()>,
+}
+
+impl DiskStorage {
+    pub fn new(dir: impl Into<PathBuf>, max_entries: Option<usize>) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            max_entries,
+            write_lock: RwLock::new(()),
+        })
+    }
+
+    fn path_for(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.val", Self::encode_key(key)))
+    }
+
+    /// Reversibly encode a key into a single safe path segment: ASCII
+    /// alphanumerics and `-`/`_` pass through, everything else (including
+    /// `.`, `/`, and non-ASCII bytes) becomes a `%XX` escape, so a key like
+    /// `../secret` can never reach the filesystem as a path traversal.
+    fn encode_key(key: &str) -> String {
+        let mut out = String::with_capacity(key.len());
+        for byte in key.as_bytes() {
+            let c = *byte as char;
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                out.push(c);
+            } else {
+                out.push_str(&format!("%{:02x}", byte));
+            }
+        }
+        out
+    }
+
+    fn decode_key(encoded: &str) -> Option<String> {
+        let mut bytes = Vec::with_capacity(encoded.len());
+        let mut chars = encoded.chars();
+        while let Some(c) = chars.next() {
+            if c == '%' {
+                let hex: String = chars.by_ref().take(2).collect();
+                bytes.push(u8::from_str_radix(&hex, 16).ok()?);
+            } else {
+                bytes.push(c as u8);
+            }
+        }
+        String::from_utf8(bytes).ok()
+    }
+
+    /// Count entries that `list_keys` would return, i.e. files ending in
+    /// `.val`. A `.tmp` file left behind by a crash between the write and
+    /// rename in `put` must not count against `max_entries` forever.
+    async fn count_entries(&self) -> Result<usize> {
+        let mut count = 0;
+        let mut entries = tokio::fs::read_dir(&self.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.file_name().to_str().is_some_and(|name| name.ends_with(".val")) {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+}
+
+#[async_trait]
+impl Storage for DiskStorage {
+    async fn get(&self, key: &str) -> Result<Option<String>> {
+        match tokio::fs::read_to_string(self.path_for(key)).await {
+            Ok(contents) => Ok(Some(contents)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn put(&self, key: &str, value: String) -> Result<()> {
+        let _guard = self.write_lock.write().await;
+        let path = self.path_for(key);
+
+        if let Some(max_entries) = self.max_entries {
+            if !tokio::fs::try_exists(&path).await.unwrap_or(false)
+                && self.count_entries().await? >= max_entries
+            {
+                return Err(anyhow::anyhow!(
+                    "Storage is full ({} entries); delete a key before adding more",
+                    max_entries
+                ));
+            }
+        }
+
+        // Write to a temp file and rename into place so a crash mid-write
+        // can't leave a partially-written value behind.
+        let tmp_path = path.with_extension("tmp");
+        tokio::fs::write(&tmp_path, value.as_bytes()).await?;
+        tokio::fs::rename(&tmp_path, &path).await?;
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        let _guard = self.write_lock.write().await;
+        match tokio::fs::remove_file(self.path_for(key)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn list_keys(&self) -> Result<Vec<String>> {
+        let mut keys = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name();
+            let Some(name) = name.to_str() else { continue };
+            let Some(stem) = name.strip_suffix(".val") else { continue };
+            if let Some(key) = Self::decode_key(stem) {
+                keys.push(key);
+            }
+        }
+        Ok(keys)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A private, per-test scratch directory under the OS temp dir; cleaned
+    /// up at the end of each test that uses it.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "htier_pubsub_storage_test_{}_{}",
+            std::process::id(),
+            name
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[tokio::test]
+    async fn memory_storage_round_trips_get_put_delete_list_keys() {
+        let storage = MemoryStorage::new();
+
+        assert_eq!(storage.get("a").await.unwrap(), None);
+
+        storage.put("a", "1".to_string()).await.unwrap();
+        storage.put("b", "2".to_string()).await.unwrap();
+        assert_eq!(storage.get("a").await.unwrap(), Some("1".to_string()));
+
+        let mut keys = storage.list_keys().await.unwrap();
+        keys.sort();
+        assert_eq!(keys, vec!["a".to_string(), "b".to_string()]);
+
+        storage.delete("a").await.unwrap();
+        assert_eq!(storage.get("a").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn disk_storage_round_trips_get_put_delete_list_keys() {
+        let dir = temp_dir("roundtrip");
+        let storage = DiskStorage::new(&dir, None).unwrap();
+
+        assert_eq!(storage.get("hello").await.unwrap(), None);
+
+        storage.put("hello", "world".to_string()).await.unwrap();
+        storage.put("other", "value".to_string()).await.unwrap();
+        assert_eq!(storage.get("hello").await.unwrap(), Some("world".to_string()));
+
+        let mut keys = storage.list_keys().await.unwrap();
+        keys.sort();
+        assert_eq!(keys, vec!["hello".to_string(), "other".to_string()]);
+
+        storage.delete("hello").await.unwrap();
+        assert_eq!(storage.get("hello").await.unwrap(), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn disk_storage_delete_of_missing_key_is_not_an_error() {
+        let dir = temp_dir("delete_missing");
+        let storage = DiskStorage::new(&dir, None).unwrap();
+
+        assert!(storage.delete("missing").await.is_ok());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn disk_storage_keys_cannot_escape_the_storage_dir() {
+        let dir = temp_dir("traversal");
+        let storage = DiskStorage::new(&dir, None).unwrap();
+
+        storage
+            .put("../../etc/passwd", "pwned".to_string())
+            .await
+            .unwrap();
+
+        // The entry must land inside `dir`, not at the literal traversal path.
+        let mut saw_entry = false;
+        let mut entries = tokio::fs::read_dir(&dir).await.unwrap();
+        while let Some(entry) = entries.next_entry().await.unwrap() {
+            saw_entry = true;
+            assert!(entry.path().starts_with(&dir));
+        }
+        assert!(saw_entry);
+
+        // And it still round-trips back out under its original key.
+        assert_eq!(
+            storage.get("../../etc/passwd").await.unwrap(),
+            Some("pwned".to_string())
+        );
+        assert_eq!(
+            storage.list_keys().await.unwrap(),
+            vec!["../../etc/passwd".to_string()]
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn disk_storage_rejects_writes_past_max_entries() {
+        let dir = temp_dir("max_entries");
+        let storage = DiskStorage::new(&dir, Some(1)).unwrap();
+
+        storage.put("a", "1".to_string()).await.unwrap();
+        let err = storage.put("b", "2".to_string()).await.unwrap_err();
+        assert!(err.to_string().contains("Storage is full"));
+
+        // Overwriting an existing key stays allowed even at the cap.
+        storage.put("a", "updated".to_string()).await.unwrap();
+        assert_eq!(storage.get("a").await.unwrap(), Some("updated".to_string()));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn orphaned_tmp_file_does_not_count_against_max_entries() {
+        let dir = temp_dir("orphaned_tmp");
+        let storage = DiskStorage::new(&dir, Some(1)).unwrap();
+
+        // Simulate a crash between `tokio::fs::write` and `tokio::fs::rename`
+        // in `put`: a `.tmp` file with no matching `.val` file.
+        tokio::fs::write(dir.join("orphan.tmp"), b"partial")
+            .await
+            .unwrap();
+
+        storage.put("a", "1".to_string()).await.unwrap();
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}